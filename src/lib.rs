@@ -27,7 +27,11 @@
 //! ```
 
 use colored::Colorize;
+use log::kv;
 use log::*;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::sync::Mutex;
 
 /// Initialize logger with fields
 /// # Example
@@ -40,12 +44,7 @@ use log::*;
 pub fn init(min_level: Level, prog_name: &str) {
     set_max_level(min_level.to_level_filter());
 
-    let logger = Logger {
-        min_level,
-        min_error_level: Level::Error,
-        prog_name: String::from(prog_name),
-        newline_sep: format!("\n{} ", "    | ".white().bold()),
-    };
+    let logger = build_logger(min_level, Level::Error, prog_name, Vec::new(), ColorMode::Always, false, None);
     if set_boxed_logger(Box::new(logger)).is_err() {
         debug!("Logger initialized twice");
     }
@@ -62,14 +61,356 @@ pub fn init(min_level: Level, prog_name: &str) {
 pub fn init_error(min_level: Level, min_error_level: Level, prog_name: &str) {
     set_max_level(min_level.to_level_filter());
 
-    let logger = Logger {
+    let logger = build_logger(min_level, min_error_level, prog_name, Vec::new(), ColorMode::Always, false, None);
+    if set_boxed_logger(Box::new(logger)).is_err() {
+        debug!("Logger initialized twice");
+    }
+}
+
+/// Initialize logger with a specific [`ColorMode`], instead of always colorizing output.
+/// # Example
+/// ```rust
+/// use log::*;
+///
+/// clang_log::init_colored(Level::Trace, "clang", clang_log::ColorMode::Auto);
+/// ```
+pub fn init_colored(min_level: Level, prog_name: &str, color_mode: ColorMode) {
+    set_max_level(min_level.to_level_filter());
+
+    let logger = build_logger(min_level, Level::Error, prog_name, Vec::new(), color_mode, false, None);
+    if set_boxed_logger(Box::new(logger)).is_err() {
+        debug!("Logger initialized twice");
+    }
+}
+
+/// Initialize logger with structured key-value rendering enabled, so attributes attached via
+/// `log`'s key-value syntax are rendered as indented `key = value` lines beneath the message.
+/// # Example
+/// ```rust
+/// use log::*;
+///
+/// clang_log::init_with_key_values(Level::Trace, "clang");
+/// error!(target: "sema", errno = 17, file = "a.c"; "redefinition");
+/// ```
+pub fn init_with_key_values(min_level: Level, prog_name: &str) {
+    set_max_level(min_level.to_level_filter());
+
+    let logger = build_logger(min_level, Level::Error, prog_name, Vec::new(), ColorMode::Always, true, None);
+    if set_boxed_logger(Box::new(logger)).is_err() {
+        debug!("Logger initialized twice");
+    }
+}
+
+/// Initialize logger with per-target level filtering, parsed from a `RUST_LOG`-style directive
+/// string.
+///
+/// `spec` is a comma-separated list of directives. Each directive is either a bare level, which
+/// sets the default level for any target not matched more specifically, or a `target_prefix=level`
+/// pair, which sets the level for any target starting with `target_prefix`. When a record's
+/// target matches more than one prefix, the longest (most specific) one wins.
+/// # Example
+/// ```rust
+/// clang_log::init_filtered("warn,clang::parser=debug,clang::codegen=trace", "clang");
+/// ```
+pub fn init_filtered(spec: &str, prog_name: &str) {
+    let directives = parse_directives(spec);
+    let max_level = directives
+        .iter()
+        .map(|(_, level)| *level)
+        .max()
+        .unwrap_or(LevelFilter::Error);
+    set_max_level(max_level);
+
+    let logger = build_logger(Level::Error, Level::Error, prog_name, directives, ColorMode::Always, false, None);
+    if set_boxed_logger(Box::new(logger)).is_err() {
+        debug!("Logger initialized twice");
+    }
+}
+
+/// Initialize logger with per-target level filtering read from an environment variable, so users
+/// get `env_logger`-like control without pulling in `env_logger`.
+/// # Example
+/// ```rust
+/// // With `CLANG_LOG=clang::parser=debug` set in the environment:
+/// clang_log::init_filtered_env("CLANG_LOG", "clang");
+/// ```
+pub fn init_filtered_env(env_var: &str, prog_name: &str) {
+    let spec = std::env::var(env_var).unwrap_or_default();
+    init_filtered(&spec, prog_name);
+}
+
+/// Default size threshold, in bytes, at which [`init_with_file`] rotates its log file.
+pub const DEFAULT_ROTATE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Initialize logger with an additional file sink: every message printed to the console is also
+/// appended, ANSI-stripped, to `path`. Rotates once the file passes [`DEFAULT_ROTATE_BYTES`]; see
+/// [`init_with_file_rotate`] to configure the threshold.
+/// # Example
+/// ```rust
+/// use log::Level;
+///
+/// clang_log::init_with_file(Level::Trace, "clang", "/tmp/clang_log.log")?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn init_with_file(min_level: Level, prog_name: &str, path: impl AsRef<Path>) -> std::io::Result<()> {
+    init_with_file_rotate(min_level, prog_name, path, DEFAULT_ROTATE_BYTES)
+}
+
+/// Like [`init_with_file`], but with a configurable rotation threshold: once the file exceeds
+/// `rotate_bytes`, it is renamed to `path.1` and a fresh file is opened at `path`.
+///
+/// Only a single rotated generation is kept: `path.1` itself is overwritten, unconditionally, the
+/// next time `path` rotates. If rotations happen faster than `path.1` is read (small
+/// `rotate_bytes`, or a long-running process even at the 10 MiB default), older history is
+/// silently lost rather than accumulating as `path.1`, `path.2`, etc. - plan to read/archive
+/// `path.1` promptly, or pick `rotate_bytes` generously, if you need to retain more than the most
+/// recent segment.
+/// # Example
+/// ```rust
+/// use log::Level;
+///
+/// clang_log::init_with_file_rotate(Level::Trace, "clang", "/tmp/clang_log.log", 1024 * 1024)?;
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn init_with_file_rotate(min_level: Level, prog_name: &str, path: impl AsRef<Path>, rotate_bytes: u64) -> std::io::Result<()> {
+    let file_sink = FileSink::open(path.as_ref(), rotate_bytes)?;
+
+    set_max_level(min_level.to_level_filter());
+    let logger = build_logger(
+        min_level,
+        Level::Error,
+        prog_name,
+        Vec::new(),
+        ColorMode::Always,
+        false,
+        Some(Mutex::new(file_sink)),
+    );
+    if set_boxed_logger(Box::new(logger)).is_err() {
+        debug!("Logger initialized twice");
+    }
+    Ok(())
+}
+
+/// A file sink that `Logger` tees its output to, alongside the console. Rotates by renaming the
+/// current file to `<path>.1` and opening a fresh one once it grows past `rotate_bytes`.
+struct FileSink {
+    file: std::fs::File,
+    path: std::path::PathBuf,
+    rotate_bytes: u64,
+    written_bytes: u64,
+}
+
+impl FileSink {
+    fn open(path: &Path, rotate_bytes: u64) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        let written_bytes = file.metadata()?.len();
+        Ok(Self { file, path: path.to_path_buf(), rotate_bytes, written_bytes })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        writeln!(self.file, "{}", line)?;
+        self.written_bytes += line.len() as u64 + 1;
+        if self.written_bytes > self.rotate_bytes {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let mut rotated_path = self.path.clone().into_os_string();
+        rotated_path.push(".1");
+        std::fs::rename(&self.path, &rotated_path)?;
+
+        match std::fs::OpenOptions::new().create(true).append(true).open(&self.path) {
+            Ok(file) => {
+                self.file = file;
+                self.written_bytes = 0;
+                Ok(())
+            }
+            Err(err) => {
+                // Couldn't reopen `path` after renaming it away - move it back so future writes
+                // through the still-open handle keep landing under the expected name instead of
+                // silently staying in `path.1` forever.
+                let _ = std::fs::rename(&rotated_path, &self.path);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Parses a comma-separated `target_prefix=level` directive string, as accepted by
+/// [`init_filtered`]. Directives that fail to parse (unknown level, empty target before `=`) are
+/// silently skipped.
+fn parse_directives(spec: &str) -> Vec<(String, LevelFilter)> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .filter_map(|directive| match directive.split_once('=') {
+            Some((target, _)) if target.trim().is_empty() => None,
+            Some((target, level)) => level
+                .trim()
+                .parse::<LevelFilter>()
+                .ok()
+                .map(|level| (target.trim().to_string(), level)),
+            None => directive
+                .parse::<LevelFilter>()
+                .ok()
+                .map(|level| (String::new(), level)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod parse_directives_tests {
+    use super::*;
+
+    #[test]
+    fn parses_bare_level_and_target_prefixes() {
+        let directives = parse_directives("warn,clang::parser=debug,clang::codegen=trace");
+        assert_eq!(
+            directives,
+            vec![
+                (String::new(), LevelFilter::Warn),
+                ("clang::parser".to_string(), LevelFilter::Debug),
+                ("clang::codegen".to_string(), LevelFilter::Trace),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_unparseable_directives() {
+        let directives = parse_directives("warn,clang::parser=bogus,clang::codegen=trace");
+        assert_eq!(
+            directives,
+            vec![(String::new(), LevelFilter::Warn), ("clang::codegen".to_string(), LevelFilter::Trace)]
+        );
+    }
+}
+
+/// Carries clang-style source location metadata (column, span, and an optional pre-read source
+/// line) through the standard `log::kv` channel, alongside a record's native `file()`/`line()`.
+/// The keys it emits (`clang_log.column`, `clang_log.span`, `clang_log.source_line`) are reserved
+/// for `clang_log`'s own use and are not rendered as user-facing key-values.
+/// Caret-diagnostic location bundled into a single value so [`log_at`] doesn't have to take
+/// `column`, `span`, and `source_line` as separate parameters.
+pub struct CaretSource<'a> {
+    /// 1-based column the caret points at.
+    pub column: usize,
+    /// Number of columns the underline spans, starting at `column`.
+    pub span: usize,
+    /// The offending source line, if the caller already has it in hand; see [`log_at`].
+    pub source_line: Option<&'a str>,
+}
+
+impl<'a> kv::Source for CaretSource<'a> {
+    fn visit<'kvs>(&'kvs self, visitor: &mut dyn kv::Visitor<'kvs>) -> Result<(), kv::Error> {
+        visitor.visit_pair(kv::Key::from("clang_log.column"), kv::Value::from(self.column as u64))?;
+        visitor.visit_pair(kv::Key::from("clang_log.span"), kv::Value::from(self.span as u64))?;
+        if let Some(source_line) = self.source_line {
+            visitor.visit_pair(kv::Key::from("clang_log.source_line"), kv::Value::from(source_line))?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects a record's key-values into an owned `Vec<(String, String)>`. Borrows handed to a
+/// `kv::Visitor` only live for the duration of the visit, but `Logger::log` needs to inspect them
+/// afterwards alongside the rest of the formatted message.
+struct KeyValueCollector(Vec<(String, String)>);
+
+impl<'kvs> kv::Visitor<'kvs> for KeyValueCollector {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
+}
+
+fn collect_key_values(record: &Record) -> Vec<(String, String)> {
+    let mut collector = KeyValueCollector(Vec::new());
+    let _ = record.key_values().visit(&mut collector);
+    collector.0
+}
+
+/// Emits a clang-style diagnostic: a `file:line:col:` location between the `prog:` name and the
+/// `level:` label of the usual header, followed by the offending source line and a caret/tilde underline beneath it spanning
+/// `span` columns starting at `column` (both 1-based).
+///
+/// `source_line`, when given, is used as-is, so callers that already have the line in hand (e.g. a
+/// parser) can avoid re-reading the file. When `None`, the line is read lazily from `file` - if
+/// that read fails, the snippet is silently omitted and only the `file:line:col:` header is shown.
+/// Prefer the [`error_at!`] macro for the common case of reading the line from disk.
+pub fn log_at(level: Level, target: &str, file: &str, line: u32, caret: CaretSource, args: std::fmt::Arguments) {
+    let record = Record::builder()
+        .level(level)
+        .target(target)
+        .file(Some(file))
+        .line(Some(line))
+        .key_values(&caret)
+        .args(args)
+        .build();
+    log::logger().log(&record);
+}
+
+/// Convenience macro for [`log_at`] at [`Level::Error`], mirroring the `target:`-prefixed form of
+/// `log`'s own macros.
+/// # Example
+/// ```rust
+/// clang_log::error_at!(file: "a.c", line: 3, column: 5, span: 1, "redefinition of 'x'");
+/// ```
+#[macro_export]
+macro_rules! error_at {
+    (target: $target:expr, file: $file:expr, line: $line:expr, column: $column:expr, span: $span:expr, $($arg:tt)+) => {
+        $crate::log_at(::log::Level::Error, $target, $file, $line, $crate::CaretSource { column: $column, span: $span, source_line: None }, format_args!($($arg)+))
+    };
+    (file: $file:expr, line: $line:expr, column: $column:expr, span: $span:expr, $($arg:tt)+) => {
+        $crate::log_at(::log::Level::Error, module_path!(), $file, $line, $crate::CaretSource { column: $column, span: $span, source_line: None }, format_args!($($arg)+))
+    };
+}
+
+/// Controls whether `Logger` colorizes its output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colorize only when the relevant stream looks like a terminal and `NO_COLOR` is unset.
+    /// Resolved once, per-stream, when the logger is initialized.
+    Auto,
+    /// Always colorize, regardless of the output stream or environment. The historical default.
+    Always,
+    /// Never colorize.
+    Never,
+}
+
+/// Resolves a [`ColorMode`] against whether a given stream is a terminal, honoring `NO_COLOR`.
+fn resolve_color(color_mode: ColorMode, stream_is_terminal: bool) -> bool {
+    match color_mode {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => stream_is_terminal && std::env::var_os("NO_COLOR").is_none(),
+    }
+}
+
+/// Builds a `Logger` from its initializer-facing parameters, resolving [`ColorMode`] into
+/// concrete per-stream booleans since stdout and stderr are detected independently.
+fn build_logger(
+    min_level: Level,
+    min_error_level: Level,
+    prog_name: &str,
+    directives: Vec<(String, LevelFilter)>,
+    color_mode: ColorMode,
+    show_key_values: bool,
+    file_sink: Option<Mutex<FileSink>>,
+) -> Logger {
+    Logger {
         min_level,
         min_error_level,
         prog_name: String::from(prog_name),
         newline_sep: format!("\n{} ", "    | ".white().bold()),
-    };
-    if set_boxed_logger(Box::new(logger)).is_err() {
-        debug!("Logger initialized twice");
+        newline_sep_plain: String::from("\n    |  "),
+        directives,
+        color_mode,
+        color_stdout: resolve_color(color_mode, std::io::stdout().is_terminal()),
+        color_stderr: resolve_color(color_mode, std::io::stderr().is_terminal()),
+        show_key_values,
+        file_sink,
     }
 }
 
@@ -84,11 +425,230 @@ pub struct Logger {
     pub prog_name: String,
     /// Constant newline separator, inserted between every newline. Avoids many allocations by storing this as a field.
     pub newline_sep: String,
+    /// Uncolored counterpart of `newline_sep`, used when the relevant stream is not colorized.
+    pub newline_sep_plain: String,
+    /// Per-target filter directives parsed from a `RUST_LOG`-style spec, e.g. via [`init_filtered`].
+    /// Empty unless set by one of the filtered initializers; the prefix `""` (if present) is the
+    /// default level applied to targets no more specific directive matches.
+    pub directives: Vec<(String, LevelFilter)>,
+    /// The color mode this logger was initialized with. See [`ColorMode`].
+    pub color_mode: ColorMode,
+    /// Whether output written via `println!` (non-error levels) should be colorized. Resolved
+    /// from `color_mode` once, at init time.
+    pub color_stdout: bool,
+    /// Whether output written via `eprintln!` (error levels) should be colorized. Resolved from
+    /// `color_mode` once, at init time.
+    pub color_stderr: bool,
+    /// Whether a record's structured key-values (`record.key_values()`) are rendered as indented
+    /// `key = value` lines beneath the message. Off by default so existing plain output is
+    /// unchanged; set via [`init_with_key_values`]. `clang_log`'s own reserved keys (used to carry
+    /// [`log_at`] location data) are never rendered this way.
+    pub show_key_values: bool,
+    /// Optional rotating file sink that every message is also written to, ANSI-stripped, in
+    /// addition to the console. Set via [`init_with_file`]/[`init_with_file_rotate`].
+    file_sink: Option<Mutex<FileSink>>,
+}
+
+impl Logger {
+    /// Resolves the effective level filter for a given target: the level of the longest matching
+    /// directive prefix, falling back to `min_level` when `directives` is empty or none match.
+    fn level_for_target(&self, target: &str) -> LevelFilter {
+        if self.directives.is_empty() {
+            return self.min_level.to_level_filter();
+        }
+
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, level)| *level)
+            .unwrap_or_else(|| self.min_level.to_level_filter())
+    }
+
+    /// Renders the `"error:"`/`"warning:"`/... label for `level`, colored when `use_color` is set
+    /// and plain otherwise.
+    fn level_label(level: Level, use_color: bool) -> String {
+        if use_color {
+            match level {
+                Level::Error => "error:".red().bold().to_string(),
+                Level::Warn => "warning:".bright_purple().bold().to_string(),
+                Level::Info => "info:".bright_black().bold().to_string(),
+                //"note".black().bold() // Clang Behavior
+                Level::Debug => "debug:".yellow().bold().to_string(), // Clang doesn't have debug logs
+                Level::Trace => "trace:".white().bold().to_string(),  // Clang doesn't have trace logs
+            }
+        } else {
+            match level {
+                Level::Error => String::from("error:"),
+                Level::Warn => String::from("warning:"),
+                Level::Info => String::from("info:"),
+                Level::Debug => String::from("debug:"),
+                Level::Trace => String::from("trace:"),
+            }
+        }
+    }
+
+    /// Resolves the source line a [`log_at`]-style record's caret should underline: `source_line`
+    /// as-is if given, otherwise read lazily from `record.file()`. Returns `None` (snippet silently
+    /// omitted) if no line was supplied and the file can't be read - e.g. missing, or the record's
+    /// line number is out of range.
+    fn resolve_source_line(record: &Record, source_line: Option<&str>) -> Option<String> {
+        if let Some(line) = source_line {
+            return Some(line.to_string());
+        }
+        let path = record.file()?;
+        let line_no = record.line()? as usize;
+        let contents = std::fs::read_to_string(path).ok()?;
+        contents.lines().nth(line_no.checked_sub(1)?).map(String::from)
+    }
+
+    /// Clamps a 1-based `column` into `source_line`'s range: at least `1`, at most one past its
+    /// last character (so the caret can point just beyond the end of the line). Shared by the
+    /// `file:line:col:` header and the caret underline so the two never disagree about where the
+    /// caret actually lands.
+    fn clamp_column(column: usize, source_line: &str) -> usize {
+        column.max(1).min(source_line.chars().count() + 1)
+    }
+
+    /// Renders the caret/tilde underline beneath an already-resolved `source_line`, for the
+    /// already-[`clamp_column`]ed 1-based `column` and `span` carried in a [`log_at`]-style
+    /// record's key-values.
+    fn render_caret_snippet(source_line: &str, column: usize, span: usize, use_color: bool) -> String {
+        let chars: Vec<char> = source_line.chars().collect();
+        // Replacing every non-tab character with a space (and leaving tabs as tabs) keeps the caret aligned under the
+        // source line regardless of the terminal's tab width, since both lines then hit the same
+        // tab stops.
+        let indent: String = chars[..column - 1]
+            .iter()
+            .map(|&c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let tildes = "~".repeat(span.max(1) - 1);
+        let underline = format!("^{}", tildes);
+        let underline = if use_color { underline.green().bold().to_string() } else { underline };
+        format!("{}{}", indent, underline)
+    }
+
+    /// Renders a single structured key-value as a clang note-style continuation line: `key = value`,
+    /// with the key dimmed/bold when `use_color` is set.
+    fn render_key_value_line(key: &str, value: &str, use_color: bool) -> String {
+        let key_display = if use_color { key.dimmed().bold().to_string() } else { key.to_string() };
+        format!("{} = {}", key_display, value)
+    }
+
+    /// Renders the full message for `record` - header, optional caret snippet, optional key-value
+    /// notes - with `use_color` controlling both the styling and which `newline_sep` variant is used
+    /// for continuation lines. Shared by the console path and the file sink so the two stay in sync
+    /// structurally, differing only in whether they're colored. `source_line`, when the record
+    /// carries a column, is resolved once by the caller and reused across both renders.
+    fn render_message(&self, record: &Record, kvs: &[(String, String)], column: Option<usize>, span: usize, source_line: Option<&str>, use_color: bool) -> String {
+        let sep = if use_color { &self.newline_sep } else { &self.newline_sep_plain };
+
+        // Clamp once against `source_line` (when we have one to clamp against) and reuse the same
+        // value for the header and the caret underline, so the two can never disagree about where
+        // the caret actually lands.
+        let column = column.map(|column| match source_line {
+            Some(source_line) => Self::clamp_column(column, source_line),
+            None => column.max(1),
+        });
+
+        let location_prefix = column
+            .map(|column| format!("{}:{}:{}: ", record.file().unwrap_or("<unknown>"), record.line().unwrap_or(0), column))
+            .unwrap_or_default();
+
+        let mut msg = format!(
+            "{}: {}{} {}",
+            self.prog_name,
+            location_prefix,
+            Self::level_label(record.level(), use_color),
+            record.args().to_string().replace('\n', sep)
+        );
+
+        if let Some(column) = column {
+            if let Some(source_line) = source_line {
+                let caret_line = Self::render_caret_snippet(source_line, column, span, use_color);
+                msg.push_str(sep);
+                msg.push_str(source_line);
+                msg.push_str(sep);
+                msg.push_str(&caret_line);
+            }
+        }
+
+        if self.show_key_values {
+            for (key, value) in kvs.iter().filter(|(key, _)| !key.starts_with("clang_log.")) {
+                msg.push_str(sep);
+                msg.push_str(&Self::render_key_value_line(key, value, use_color));
+            }
+        }
+
+        msg
+    }
+}
+
+#[cfg(test)]
+mod logger_tests {
+    use super::*;
+
+    fn logger_with_directives(directives: Vec<(String, LevelFilter)>) -> Logger {
+        build_logger(Level::Error, Level::Error, "clang", directives, ColorMode::Never, false, None)
+    }
+
+    #[test]
+    fn level_for_target_falls_back_to_min_level_without_directives() {
+        let logger = logger_with_directives(Vec::new());
+        assert_eq!(logger.level_for_target("clang::parser"), LevelFilter::Error);
+    }
+
+    #[test]
+    fn level_for_target_picks_longest_matching_prefix() {
+        let logger = logger_with_directives(vec![
+            (String::new(), LevelFilter::Warn),
+            ("clang::parser".to_string(), LevelFilter::Debug),
+            ("clang::parser::lexer".to_string(), LevelFilter::Trace),
+        ]);
+        assert_eq!(logger.level_for_target("clang::parser::lexer::token"), LevelFilter::Trace);
+        assert_eq!(logger.level_for_target("clang::parser::sema"), LevelFilter::Debug);
+        assert_eq!(logger.level_for_target("clang::codegen"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn clamp_column_keeps_in_range_column_unchanged() {
+        assert_eq!(Logger::clamp_column(3, "abcdef"), 3);
+    }
+
+    #[test]
+    fn clamp_column_clamps_past_end_of_line_to_one_past_the_last_character() {
+        // "abcdef".len() == 6, so the caret can land at most at column 7 (just past the 'f').
+        assert_eq!(Logger::clamp_column(999, "abcdef"), 7);
+    }
+
+    #[test]
+    fn clamp_column_clamps_zero_up_to_one() {
+        assert_eq!(Logger::clamp_column(0, "abcdef"), 1);
+    }
+
+    #[test]
+    fn render_caret_snippet_underlines_clamped_column() {
+        let snippet = Logger::render_caret_snippet("abcdef", Logger::clamp_column(999, "abcdef"), 1, false);
+        assert_eq!(snippet, "      ^");
+    }
+
+    #[test]
+    fn render_message_header_and_caret_agree_on_a_past_end_of_line_column() {
+        // Regression test: the `file:line:col:` header used to print the raw, un-clamped column
+        // while the caret line underlined source_line's own clamped copy, so a past-end-of-line
+        // column produced a self-contradictory diagnostic (header claims col 999, caret at col 7).
+        let logger = logger_with_directives(Vec::new());
+        let record = Record::builder().level(Level::Error).file(Some("a.c")).line(Some(2)).args(format_args!("redefinition")).build();
+        let msg = logger.render_message(&record, &[], Some(999), 1, Some("abcdef"), false);
+        let lines: Vec<&str> = msg.lines().collect();
+        assert!(lines[0].contains("a.c:2:7:"), "header should report the clamped column, got: {}", lines[0]);
+        assert!(lines[2].ends_with("      ^"), "caret should underline the same clamped column, got: {}", lines[2]);
+    }
 }
 
 impl Log for Logger {
     fn enabled(&self, metadata: &Metadata) -> bool {
-        metadata.level() <= self.min_level
+        metadata.level() <= self.level_for_target(metadata.target())
     }
 
     fn log(&self, record: &Record) {
@@ -96,35 +656,36 @@ impl Log for Logger {
             return;
         }
 
-        let msg = format!(
-            "{}: {} {}",
-            self.prog_name,
-            match record.level() {
-                Level::Error => {
-                    "error:".red().bold()
-                }
-                Level::Warn => {
-                    "warning:".bright_purple().bold()
-                }
-                Level::Info => {
-                    "info:".bright_black().bold()
-                    //"note".black().bold() // Clang Behavior
-                }
-                Level::Debug => {
-                    "debug:".yellow().bold() // Clang doesn't have debug logs
-                }
-                Level::Trace => {
-                    "trace:".white().bold() // Clang doesn't have trace logs
-                }
-            },
-            record.args().to_string().replace('\n', &self.newline_sep)
-        );
+        let is_error = record.level() >= self.min_error_level;
+        let use_color = if is_error { self.color_stderr } else { self.color_stdout };
+
+        let kvs = collect_key_values(record);
+        let column = kvs
+            .iter()
+            .find(|(key, _)| key == "clang_log.column")
+            .and_then(|(_, value)| value.parse::<usize>().ok());
+        let span = kvs
+            .iter()
+            .find(|(key, _)| key == "clang_log.span")
+            .and_then(|(_, value)| value.parse::<usize>().ok())
+            .unwrap_or(1);
+        let source_line_override = kvs.iter().find(|(key, _)| key == "clang_log.source_line").map(|(_, value)| value.as_str());
+        let source_line = column.and_then(|_| Self::resolve_source_line(record, source_line_override));
+
+        let msg = self.render_message(record, &kvs, column, span, source_line.as_deref(), use_color);
 
-        if record.level() >= self.min_error_level {
+        if is_error {
             eprintln!("{}", msg);
         } else {
             println!("{}", msg);
         }
+
+        if let Some(file_sink) = &self.file_sink {
+            let plain_msg = self.render_message(record, &kvs, column, span, source_line.as_deref(), false);
+            if let Ok(mut file_sink) = file_sink.lock() {
+                let _ = file_sink.write_line(&plain_msg);
+            }
+        }
     }
 
     fn flush(&self) {}